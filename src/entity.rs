@@ -5,19 +5,38 @@
 
 pub mod mesh;
 
+mod loader;
+mod watch;
+
 use crate::{
-  runtime::RuntimeMsg,
-  system::{resource::ResourceManager, system_init, Addr, MsgQueue, Subscriber, System, SystemUID},
+  system::{
+    dispatcher::Dispatcher,
+    resource::{Handle, ResourceManager},
+    supervisor::SupervisorMsg,
+    system_init, Ack, Addr, Envelope, MsgQueue, RecvOutcome, Request, System, SystemUID,
+  },
 };
+pub use loader::{EntityLoader, LoadError, LoaderRegistry};
 use colored::Colorize as _;
 use mesh::Mesh;
 use std::{
+  collections::HashMap,
   ffi::OsStr,
   fs::read_dir,
   path::{Path, PathBuf},
   sync::Arc,
   thread,
+  time::Duration,
 };
+use watch::{FsEvent, FsWatcher};
+
+/// How long to wait on the message queue before polling the filesystem watcher.
+const MSG_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Topic a mesh is (re)loaded under.
+const TOPIC_MESH_LOADED: &str = "mesh.loaded";
+/// Topic a mesh is removed under.
+const TOPIC_MESH_CHANGED: &str = "mesh.changed";
 
 /// All possible entities.
 #[derive(Debug)]
@@ -30,41 +49,92 @@ pub enum Entity {
 pub enum EntityMsg {
   /// Kill message.
   Kill,
+  /// A resource at this path was just (re)loaded.
+  AssetLoaded(PathBuf),
 }
 
 #[derive(Clone, Debug)]
 pub enum EntityEvent {
   HelloWorld,
+  /// A resource was (re)loaded, either from the initial traversal or a hot-reload.
+  ResourceLoaded(PathBuf),
+  /// A resource was removed from disk and its handle evicted.
+  ResourceRemoved(PathBuf),
 }
 
 /// The [`Entity`] system.
 pub struct EntitySystem {
   uid: SystemUID,
-  runtime_addr: Addr<RuntimeMsg>,
+  /// Address of the supervisor this system registered under; used to report our own exit.
+  supervisor_addr: Addr<SupervisorMsg>,
   /// Directory where all scarce resources this entity system knows about live in.
   root_dir: PathBuf,
   resources: ResourceManager<Entity>,
-  addr: Addr<EntityMsg>,
-  msg_queue: MsgQueue<EntityMsg>,
-  subscribers: Vec<Box<dyn Subscriber<EntityEvent>>>,
+  /// Handles of currently loaded resources, keyed by the path they were loaded from, so that a hot-reload can
+  /// update a handle in place instead of handing out a new one.
+  loaded: HashMap<PathBuf, Handle>,
+  /// Address others use to deliver [`EntityMsg`]s, wrapped in an [`Envelope`] that carries the sender's identity.
+  addr: Addr<Envelope<EntityMsg>>,
+  msg_queue: MsgQueue<Envelope<EntityMsg>>,
+  /// Dedicated mailbox for handle lookups, reachable via [`Addr::request`]/[`Addr::request_timeout`].
+  lookup_addr: Addr<Request<PathBuf, Option<Handle>>>,
+  lookup_queue: MsgQueue<Request<PathBuf, Option<Handle>>>,
+  /// This system's own [`System::ack_addr`]; acks for envelopes it sends itself land here.
+  ack_addr: Addr<Ack>,
+  ack_queue: MsgQueue<Ack>,
+  /// Routes published [`EntityEvent`]s to subscribers of the relevant topic (e.g. a renderer only cares about
+  /// `mesh.changed`, while a logger subscribes to everything).
+  dispatcher: Dispatcher<EntityEvent>,
+  /// Watches `root_dir` on a dedicated thread and reports debounced changes.
+  fs_watcher: Option<FsWatcher>,
+  /// Maps file extensions to the loader that should handle them.
+  loaders: LoaderRegistry,
 }
 
 impl EntitySystem {
   /// Create a new [`EntitySystem`].
-  pub fn new(runtime_addr: Addr<RuntimeMsg>, uid: SystemUID, root_dir: impl AsRef<Path>) -> Self {
+  ///
+  /// `loaders` decides how files found under `root_dir` get loaded; use [`LoaderRegistry::new`] for just the
+  /// default `.obj` loader, or [`LoaderRegistry::from_manifest_dir`] to also pick up `root_dir`'s manifest file.
+  pub fn new(
+    supervisor_addr: Addr<SupervisorMsg>,
+    uid: SystemUID,
+    root_dir: impl AsRef<Path>,
+    loaders: LoaderRegistry,
+  ) -> Self {
     let (addr, msg_queue) = system_init();
+    let (lookup_addr, lookup_queue) = system_init();
+    let (ack_addr, ack_queue) = system_init();
+    let root_dir = root_dir.as_ref().to_owned();
+    let fs_watcher = FsWatcher::spawn(&root_dir);
+
+    if fs_watcher.is_none() {
+      log::warn!("hot-reloading disabled for {}", root_dir.display().to_string().purple().italic());
+    }
 
     Self {
       uid,
-      runtime_addr,
-      root_dir: root_dir.as_ref().to_owned(),
+      supervisor_addr,
+      root_dir,
       resources: ResourceManager::new(),
+      loaded: HashMap::new(),
       addr,
       msg_queue,
-      subscribers: Vec::new(),
+      lookup_addr,
+      lookup_queue,
+      ack_addr,
+      ack_queue,
+      dispatcher: Dispatcher::new(),
+      fs_watcher,
+      loaders,
     }
   }
 
+  /// Address other systems can use to ask for the [`Handle`] currently assigned to a resource path, if any.
+  pub fn lookup_addr(&self) -> Addr<Request<PathBuf, Option<Handle>>> {
+    self.lookup_addr.clone()
+  }
+
   /// Start the system.
   ///
   /// This method will first tries to load all the resources it can from `root_dir`, then will stay in an idle mode where it will:
@@ -77,14 +147,116 @@ impl EntitySystem {
 
     // main loop
     loop {
-      match self.msg_queue.recv() {
-        Some(EntityMsg::Kill) | None => {
-          self
-            .runtime_addr
-            .send_msg(RuntimeMsg::SystemExit(self.uid))
-            .unwrap();
+      match self.msg_queue.recv_timeout(MSG_POLL_INTERVAL) {
+        RecvOutcome::Msg(envelope) => {
+          log::debug!(
+            "received message {} from {}",
+            envelope.id.to_string().blue(),
+            envelope.from.to_string().blue(),
+          );
+
+          let stop = self.handle_msg(envelope.msg.clone());
+
+          if envelope.ack().is_err() {
+            log::warn!("cannot ack message {} from {}", envelope.id, envelope.from);
+          }
+
+          if stop {
+            break;
+          }
+        }
+
+        RecvOutcome::Disconnected => {
+          // the supervisor itself might already be gone (e.g. it escalated and tore itself down); failing to
+          // report our own exit is not a reason to panic on our way out
+          if self
+            .supervisor_addr
+            .send_msg(SupervisorMsg::ChildExited(self.uid))
+            .is_err()
+          {
+            log::warn!("cannot report exit: supervisor is gone");
+          }
           break;
         }
+
+        // nothing on the message queue within the poll interval; give the filesystem watcher a turn
+        RecvOutcome::Timeout => {}
+      }
+
+      self.drain_lookups();
+      self.drain_acks();
+      self.drain_fs_events();
+    }
+  }
+
+  /// Handle a single [`EntityMsg`]. Returns `true` if the main loop should stop.
+  fn handle_msg(&mut self, msg: EntityMsg) -> bool {
+    match msg {
+      EntityMsg::Kill => {
+        // same reasoning as the `RecvOutcome::Disconnected` branch in `start`: the supervisor may already be torn
+        // down, and that's not a reason to panic on our own way out
+        if self
+          .supervisor_addr
+          .send_msg(SupervisorMsg::ChildExited(self.uid))
+          .is_err()
+        {
+          log::warn!("cannot report exit: supervisor is gone");
+        }
+        true
+      }
+
+      EntityMsg::AssetLoaded(path) => {
+        log::debug!("{} finished loading", path.display().to_string().purple().italic());
+        false
+      }
+    }
+  }
+
+  /// Drain and log every pending [`Ack`].
+  fn drain_acks(&mut self) {
+    while let Some(ack) = self.ack_queue.try_recv() {
+      log::debug!("asset load {} acked", ack.0);
+    }
+  }
+
+  /// Drain and answer every pending handle lookup request.
+  fn drain_lookups(&mut self) {
+    while let Some(req) = self.lookup_queue.try_recv() {
+      self.handle_lookup(req);
+    }
+  }
+
+  /// Answer a single handle lookup request.
+  fn handle_lookup(&mut self, req: Request<PathBuf, Option<Handle>>) {
+    let handle = self.loaded.get(&req.query).cloned();
+
+    if req.reply(handle).is_err() {
+      log::warn!("cannot reply to handle lookup request: requester is gone");
+    }
+  }
+
+  /// Drain and act on every debounced filesystem event currently pending.
+  fn drain_fs_events(&mut self) {
+    let Some(fs_watcher) = self.fs_watcher.as_ref() else {
+      return;
+    };
+
+    while let Some(event) = fs_watcher.try_recv() {
+      match event {
+        FsEvent::Changed(path) => {
+          if let Some(ext) = path.extension().and_then(OsStr::to_str) {
+            let ext = ext.to_owned();
+            self.extension_based_dispatch(&ext, &path);
+          }
+        }
+
+        FsEvent::Removed(path) => {
+          if let Some(handle) = self.loaded.remove(&path) {
+            self.resources.evict(handle);
+            log::info!("evicted {}", path.display().to_string().purple().italic());
+            self.publish(EntityEvent::ResourceRemoved(path));
+          }
+        }
       }
     }
   }
@@ -126,11 +298,12 @@ impl EntitySystem {
     }
   }
 
-  /// Dispatch entity loading based on the extension of a file.
+  /// Dispatch entity loading based on the extension of a file, looking up the relevant loader in the
+  /// [`LoaderRegistry`] instead of hard-coding a format per extension.
   fn extension_based_dispatch(&mut self, ext: &str, path: &Path) {
-    match ext {
-      "obj" => self.load_obj(path),
-      _ => log::warn!(
+    match self.loaders.get(ext) {
+      Some(loader) => self.load_with(loader.as_ref(), path),
+      None => log::warn!(
         "unknown extension {} for path {}",
         ext.blue().italic(),
         path.display().to_string().purple().italic(),
@@ -138,36 +311,76 @@ impl EntitySystem {
     }
   }
 
-  /// Load .obj files.
-  fn load_obj(&mut self, path: &Path) {
-    match Mesh::load_from_path(path) {
-      Ok(mesh) => {
+  /// Load `path` with `loader`, (re)assigning its handle and publishing the resulting event.
+  fn load_with(&mut self, loader: &dyn EntityLoader, path: &Path) {
+    match loader.load(path) {
+      Ok(entity) => {
         let path_name = path.display().to_string();
-        let path = path_name.purple().italic();
-        log::info!("{} {}", "loaded".green().bold(), path);
+        let path_display = path_name.purple().italic();
+        log::info!("{} {}", "loaded".green().bold(), path_display);
+
+        // if this path was already loaded (a hot-reload), update its handle in place so that downstream holders
+        // keep using the same handle; otherwise hand out a fresh one
+        match self.loaded.get(path) {
+          Some(handle) => {
+            self.resources.update(handle, entity);
+            log::debug!("updated handle {} in place", handle.to_string().green().bold());
+          }
 
-        let h = self.resources.wrap(Entity::Mesh(mesh), path_name);
-        log::debug!("assigned {} handle {}", path, h.to_string().green().bold());
+          None => {
+            let handle = self.resources.wrap(entity, path_name);
+            log::debug!(
+              "assigned {} handle {}",
+              path_display,
+              handle.to_string().green().bold()
+            );
+            self.loaded.insert(path.to_owned(), handle);
+          }
+        }
+
+        self.publish(EntityEvent::ResourceLoaded(path.to_owned()));
+
+        // sent to ourselves so the load gets acked back to `ack_queue`
+        if self
+          .addr
+          .send_msg_from(EntityMsg::AssetLoaded(path.to_owned()), &*self)
+          .is_err()
+        {
+          log::warn!("cannot record load of {}: mailbox is gone", path_display);
+        }
       }
 
       Err(err) => {
         log::error!(
-          "cannot load OBJ {}: {}",
+          "cannot load {}: {}",
           path.display().to_string().purple().italic(),
           err,
         );
       }
     }
   }
+
+  /// Subscribe `addr` to a single topic (see the `TOPIC_*` constants) instead of every [`EntityEvent`].
+  pub fn subscribe_topic(&mut self, topic: &str, addr: Addr<EntityEvent>) {
+    self.dispatcher.subscribe_topic(topic, addr);
+  }
 }
 
 impl System<EntityEvent> for EntitySystem {
-  type Addr = Addr<EntityMsg>;
+  type Addr = Addr<Envelope<EntityMsg>>;
 
-  fn system_addr(&self) -> Addr<EntityMsg> {
+  fn system_addr(&self) -> Addr<Envelope<EntityMsg>> {
     self.addr.clone()
   }
 
+  fn system_uid(&self) -> SystemUID {
+    self.uid
+  }
+
+  fn ack_addr(&self) -> Option<Addr<Ack>> {
+    Some(self.ack_addr.clone())
+  }
+
   fn startup(self) {
     // move into a thread for greater good
     let _ = thread::spawn(move || {
@@ -175,13 +388,20 @@ impl System<EntityEvent> for EntitySystem {
     });
   }
 
-  fn subscribe(&mut self, subscriber: impl Subscriber<EntityEvent> + 'static) {
-    self.subscribers.push(Box::new(subscriber));
+  /// Subscribe to every [`EntityEvent`], regardless of topic.
+  fn subscribe(&mut self, addr: Addr<EntityEvent>) {
+    self.dispatcher.subscribe_all(addr);
   }
 
+  /// Publish `event` under the topic matching its variant (see [`EntitySystem::subscribe_topic`] to subscribe to a
+  /// subset instead of everything).
   fn publish(&self, event: EntityEvent) {
-    for sub in &self.subscribers {
-      sub.recv_msg(event.clone());
-    }
+    let topic = match &event {
+      EntityEvent::HelloWorld => "entity.hello",
+      EntityEvent::ResourceLoaded(_) => TOPIC_MESH_LOADED,
+      EntityEvent::ResourceRemoved(_) => TOPIC_MESH_CHANGED,
+    };
+
+    self.dispatcher.publish_topic(topic, event);
   }
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+//! Config-driven loader registry.
+//!
+//! Instead of a hard-coded `match` on file extension, entities are loaded through a [`LoaderRegistry`] mapping
+//! extensions to [`EntityLoader`] trait objects. The registry can be populated from a declarative manifest file
+//! living in the entity system's `root_dir`, so users can register formats and tune existing ones (scale,
+//! coordinate-system flips, …) without touching code.
+
+use crate::entity::{mesh::Mesh, Entity};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, fs, path::Path, sync::Arc};
+
+/// Name of the manifest file read from an entity system's `root_dir`.
+pub const MANIFEST_FILE_NAME: &str = "entities.toml";
+
+/// Something that can turn a file on disk into an [`Entity`].
+pub trait EntityLoader: fmt::Debug + Send + Sync {
+  /// Load the entity at `path`.
+  fn load(&self, path: &Path) -> Result<Entity, LoadError>;
+}
+
+/// Errors that might occur while loading an entity.
+#[derive(Debug)]
+pub enum LoadError {
+  /// The underlying file could not be read.
+  Io(std::io::Error),
+  /// Anything else the loader wants to report (e.g. a malformed mesh).
+  Other(String),
+}
+
+impl fmt::Display for LoadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      LoadError::Io(err) => write!(f, "I/O error: {}", err),
+      LoadError::Other(msg) => write!(f, "{}", msg),
+    }
+  }
+}
+
+impl From<std::io::Error> for LoadError {
+  fn from(err: std::io::Error) -> Self {
+    LoadError::Io(err)
+  }
+}
+
+/// Per-loader options read from the manifest, tuning how a format gets imported.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LoaderOptions {
+  /// Uniform scale applied to the loaded entity.
+  #[serde(default = "default_scale")]
+  pub scale: f32,
+  /// Whether to flip the Y and Z axes on load, to account for differing coordinate-system conventions.
+  #[serde(default)]
+  pub flip_yz: bool,
+}
+
+impl Default for LoaderOptions {
+  fn default() -> Self {
+    LoaderOptions {
+      scale: default_scale(),
+      flip_yz: false,
+    }
+  }
+}
+
+fn default_scale() -> f32 {
+  1.0
+}
+
+/// One `[[loader]]` entry in the manifest: which extension it applies to, which built-in format handles it, and
+/// that format's options.
+#[derive(Clone, Debug, Deserialize)]
+struct LoaderEntry {
+  /// File extension this entry applies to (without the leading dot).
+  extension: String,
+  /// Name of the built-in format that should handle this extension (e.g. `"obj"`).
+  format: String,
+  #[serde(flatten)]
+  options: LoaderOptions,
+}
+
+/// Top-level shape of the manifest file.
+#[derive(Clone, Debug, Default, Deserialize)]
+struct Manifest {
+  #[serde(default, rename = "loader")]
+  loaders: Vec<LoaderEntry>,
+}
+
+/// The built-in `.obj` loader.
+#[derive(Debug, Default)]
+struct ObjLoader {
+  options: LoaderOptions,
+}
+
+impl EntityLoader for ObjLoader {
+  fn load(&self, path: &Path) -> Result<Entity, LoadError> {
+    let mut mesh = Mesh::load_from_path(path).map_err(|err| LoadError::Other(err.to_string()))?;
+
+    if self.options.flip_yz {
+      mesh.flip_yz();
+    }
+
+    if self.options.scale != 1.0 {
+      mesh.scale(self.options.scale);
+    }
+
+    Ok(Entity::Mesh(mesh))
+  }
+}
+
+/// Build the [`EntityLoader`] for a built-in format by name, if it's known.
+fn build_loader(format: &str, options: LoaderOptions) -> Option<Arc<dyn EntityLoader>> {
+  match format {
+    "obj" => Some(Arc::new(ObjLoader { options })),
+    _ => None,
+  }
+}
+
+/// Maps file extensions to the [`EntityLoader`] that should handle them.
+#[derive(Default)]
+pub struct LoaderRegistry {
+  loaders: HashMap<String, Arc<dyn EntityLoader>>,
+}
+
+impl LoaderRegistry {
+  /// Build a registry with just the default `.obj` loader.
+  pub fn new() -> Self {
+    let mut registry = Self::default();
+    registry.register("obj", build_loader("obj", LoaderOptions::default()).unwrap());
+    registry
+  }
+
+  /// Build a registry seeded with the defaults, then extended/tuned from the manifest file in `root_dir`, if any.
+  pub fn from_manifest_dir(root_dir: &Path) -> Self {
+    let mut registry = Self::new();
+    let manifest_path = root_dir.join(MANIFEST_FILE_NAME);
+
+    if !manifest_path.is_file() {
+      return registry;
+    }
+
+    match fs::read_to_string(&manifest_path) {
+      Ok(contents) => match toml::from_str::<Manifest>(&contents) {
+        Ok(manifest) => registry.apply_manifest(manifest),
+
+        Err(err) => log::warn!("cannot parse manifest {}: {}", manifest_path.display(), err),
+      },
+
+      Err(err) => log::warn!("cannot read manifest {}: {}", manifest_path.display(), err),
+    }
+
+    registry
+  }
+
+  fn apply_manifest(&mut self, manifest: Manifest) {
+    for entry in manifest.loaders {
+      match build_loader(&entry.format, entry.options) {
+        Some(loader) => {
+          self.loaders.insert(entry.extension, loader);
+        }
+
+        None => log::warn!(
+          "manifest references unknown loader format {} for extension {}",
+          entry.format,
+          entry.extension,
+        ),
+      }
+    }
+  }
+
+  /// Register `loader` for `extension`, replacing whatever was registered for it before.
+  pub fn register(&mut self, extension: &str, loader: Arc<dyn EntityLoader>) {
+    self.loaders.insert(extension.to_owned(), loader);
+  }
+
+  /// Get the loader registered for `extension`, if any.
+  pub fn get(&self, extension: &str) -> Option<Arc<dyn EntityLoader>> {
+    self.loaders.get(extension).cloned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn manifest_parses_overrides_and_defaults() {
+    let manifest: Manifest = toml::from_str(
+      r#"
+      [[loader]]
+      extension = "obj"
+      format = "obj"
+      scale = 2.5
+      flip_yz = true
+
+      [[loader]]
+      extension = "fbx"
+      format = "fbx"
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(manifest.loaders.len(), 2);
+    assert_eq!(manifest.loaders[0].options.scale, 2.5);
+    assert!(manifest.loaders[0].options.flip_yz);
+    assert_eq!(manifest.loaders[1].options.scale, default_scale());
+    assert!(!manifest.loaders[1].options.flip_yz);
+  }
+
+  #[test]
+  fn build_loader_rejects_unknown_format() {
+    assert!(build_loader("fbx", LoaderOptions::default()).is_none());
+  }
+
+  #[test]
+  fn apply_manifest_skips_unknown_formats_but_keeps_known_ones() {
+    let mut registry = LoaderRegistry::default();
+    registry.apply_manifest(Manifest {
+      loaders: vec![
+        LoaderEntry {
+          extension: "obj".into(),
+          format: "obj".into(),
+          options: LoaderOptions { scale: 3.0, flip_yz: true },
+        },
+        LoaderEntry {
+          extension: "fbx".into(),
+          format: "fbx".into(),
+          options: LoaderOptions::default(),
+        },
+      ],
+    });
+
+    assert!(registry.get("obj").is_some());
+    assert!(registry.get("fbx").is_none());
+  }
+
+  #[test]
+  fn from_manifest_dir_without_a_manifest_file_keeps_just_the_default_obj_loader() {
+    let dir = std::env::temp_dir().join(format!("entity-loader-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let registry = LoaderRegistry::from_manifest_dir(&dir);
+    assert!(registry.get("obj").is_some());
+    assert!(registry.get("fbx").is_none());
+
+    fs::remove_dir_all(&dir).ok();
+  }
+}
@@ -0,0 +1,122 @@
+//! Filesystem watching for hot-reloading entities.
+//!
+//! This module spawns a dedicated OS thread that registers a recursive watch on a root directory and forwards
+//! debounced events back to the owning [`EntitySystem`](crate::entity::EntitySystem). Editors tend to emit bursts of
+//! create/modify/rename events for a single save, so raw events are coalesced by path: an event for a path is only
+//! forwarded once no further event for that same path has arrived within [`DEBOUNCE_WINDOW`].
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::mpsc,
+  thread,
+  time::{Duration, Instant},
+};
+
+/// Window during which further events for the same path reset its debounce timer.
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// A debounced filesystem event, ready to be acted upon.
+#[derive(Clone, Debug)]
+pub enum FsEvent {
+  /// A path was created or modified and should be (re)loaded.
+  Changed(PathBuf),
+  /// A path was removed and its handle should be evicted.
+  Removed(PathBuf),
+}
+
+/// A background filesystem watcher.
+///
+/// Owns the dedicated watching thread and the underlying OS watch handle for as long as it is alive; dropping it
+/// tears both down.
+pub struct FsWatcher {
+  events: mpsc::Receiver<FsEvent>,
+  // kept alive so the OS watch isn’t torn down; never read directly
+  _watcher: RecommendedWatcher,
+}
+
+impl FsWatcher {
+  /// Spawn a new [`FsWatcher`] recursively watching `root_dir`.
+  pub fn spawn(root_dir: impl AsRef<Path>) -> Option<Self> {
+    let root_dir = root_dir.as_ref().to_owned();
+    let (raw_tx, raw_rx) = mpsc::channel();
+
+    let mut watcher = match notify::recommended_watcher(raw_tx) {
+      Ok(watcher) => watcher,
+
+      Err(err) => {
+        log::error!("cannot create filesystem watcher: {}", err);
+        return None;
+      }
+    };
+
+    if let Err(err) = watcher.watch(&root_dir, RecursiveMode::Recursive) {
+      log::error!("cannot watch {}: {}", root_dir.display(), err);
+      return None;
+    }
+
+    let (events_tx, events_rx) = mpsc::channel();
+    thread::spawn(move || Self::debounce_loop(raw_rx, events_tx));
+
+    Some(Self {
+      events: events_rx,
+      _watcher: watcher,
+    })
+  }
+
+  /// Drain raw events, coalescing them per-path until each one has been quiet for [`DEBOUNCE_WINDOW`].
+  fn debounce_loop(
+    raw_rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    debounced_tx: mpsc::Sender<FsEvent>,
+  ) {
+    let mut pending: HashMap<PathBuf, (FsEvent, Instant)> = HashMap::new();
+
+    loop {
+      match raw_rx.recv_timeout(DEBOUNCE_WINDOW) {
+        Ok(Ok(event)) => Self::record_event(&mut pending, event),
+
+        Ok(Err(err)) => log::warn!("filesystem watch error: {}", err),
+
+        // nothing arrived within the debounce window; time to flush anything that’s settled
+        Err(mpsc::RecvTimeoutError::Timeout) => {}
+
+        // sender side (the watcher) got dropped, we’re done
+        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+      }
+
+      Self::flush_settled(&mut pending, &debounced_tx);
+    }
+  }
+
+  fn record_event(pending: &mut HashMap<PathBuf, (FsEvent, Instant)>, event: notify::Event) {
+    for path in event.paths {
+      let fs_event = match event.kind {
+        EventKind::Remove(_) => FsEvent::Removed(path.clone()),
+        _ => FsEvent::Changed(path.clone()),
+      };
+
+      pending.insert(path, (fs_event, Instant::now()));
+    }
+  }
+
+  fn flush_settled(pending: &mut HashMap<PathBuf, (FsEvent, Instant)>, debounced_tx: &mpsc::Sender<FsEvent>) {
+    let settled: Vec<_> = pending
+      .iter()
+      .filter(|(_, (_, last_seen))| last_seen.elapsed() >= DEBOUNCE_WINDOW)
+      .map(|(path, _)| path.clone())
+      .collect();
+
+    for path in settled {
+      if let Some((event, _)) = pending.remove(&path) {
+        // the main loop might be gone already (system shutting down); nothing to do about it
+        let _ = debounced_tx.send(event);
+      }
+    }
+  }
+
+  /// Non-blockingly poll for the next debounced event.
+  pub fn try_recv(&self) -> Option<FsEvent> {
+    self.events.try_recv().ok()
+  }
+}
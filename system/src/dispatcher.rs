@@ -0,0 +1,104 @@
+//! Topic-based event dispatch.
+//!
+//! Where [`System::publish`](crate::System::publish) blasts every event to every subscriber, a [`Dispatcher`] lets
+//! subscribers opt into a subset of events by subscribing to named topics (e.g. `"mesh.loaded"`,
+//! `"mesh.changed"`). Topic names are interned once into small integer ids via a shared, thread-safe
+//! [`TopicInterner`], so subscription sets key on a cheap [`TopicId`] rather than repeatedly hashing and comparing
+//! `String`s. A broadcast-to-all subscription list is kept alongside topics, preserving the old flat fan-out
+//! behavior for subscribers that want every event regardless of topic.
+
+use crate::Addr;
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
+
+/// An interned topic id.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TopicId(u32);
+
+#[derive(Debug, Default)]
+struct InternTable {
+  ids: HashMap<String, u32>,
+  next: u32,
+}
+
+/// A shared, thread-safe interner mapping topic names to small integer [`TopicId`]s.
+#[derive(Clone, Debug, Default)]
+pub struct TopicInterner {
+  table: Arc<Mutex<InternTable>>,
+}
+
+impl TopicInterner {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Intern `topic`, returning its id. Interning the same topic twice returns the same id.
+  pub fn intern(&self, topic: &str) -> TopicId {
+    let mut table = self.table.lock().unwrap();
+
+    if let Some(&id) = table.ids.get(topic) {
+      return TopicId(id);
+    }
+
+    let id = table.next;
+    table.next += 1;
+    table.ids.insert(topic.to_owned(), id);
+
+    TopicId(id)
+  }
+}
+
+/// Maps topics to the subscribers that should receive events published under them.
+pub struct Dispatcher<E> {
+  interner: TopicInterner,
+  topics: HashMap<TopicId, Vec<Addr<E>>>,
+  /// Subscribers that receive every event regardless of topic.
+  broadcast: Vec<Addr<E>>,
+}
+
+impl<E: Clone> Dispatcher<E> {
+  pub fn new() -> Self {
+    Self {
+      interner: TopicInterner::new(),
+      topics: HashMap::new(),
+      broadcast: Vec::new(),
+    }
+  }
+
+  /// Subscribe `addr` to `topic`; it will receive every event subsequently published under that topic.
+  pub fn subscribe_topic(&mut self, topic: &str, addr: Addr<E>) {
+    let id = self.interner.intern(topic);
+    self.topics.entry(id).or_default().push(addr);
+  }
+
+  /// Subscribe `addr` to every topic, preserving the old flat fan-out behavior.
+  pub fn subscribe_all(&mut self, addr: Addr<E>) {
+    self.broadcast.push(addr);
+  }
+
+  /// Publish `event` under `topic` to its subscribers, plus every broadcast subscriber.
+  pub fn publish_topic(&self, topic: &str, event: E) {
+    let id = self.interner.intern(topic);
+
+    if let Some(subs) = self.topics.get(&id) {
+      for addr in subs {
+        let _ = addr.send_msg(event.clone());
+      }
+    }
+
+    for addr in &self.broadcast {
+      let _ = addr.send_msg(event.clone());
+    }
+  }
+}
+
+impl<E> Default for Dispatcher<E>
+where
+  E: Clone,
+{
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -5,10 +5,14 @@
 
 use std::fmt;
 use std::sync::mpsc;
+use std::time::Duration;
 
 use rand::{thread_rng, Rng as _};
 
+pub mod dispatcher;
+pub mod remote;
 pub mod resource;
+pub mod supervisor;
 
 /// Systems.
 ///
@@ -40,10 +44,23 @@ where
 
   /// Subscribe a system that will receive events.
   fn subscribe(&mut self, addr: Addr<E>);
+
+  /// Get the [`SystemUID`] of this system.
+  fn system_uid(&self) -> SystemUID;
+
+  /// Get the address acknowledgements should be sent to when this system sends an [`Envelope`], if it wants one.
+  ///
+  /// Defaults to `None`: a system only needs to override this if it actually keeps a mailbox for [`Ack`]s around.
+  fn ack_addr(&self) -> Option<Addr<Ack>> {
+    None
+  }
 }
 
 /// UID of a system.
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+///
+/// Serializable so it can be exchanged during a [`remote`](crate::remote) handshake, in addition to being used
+/// locally to key a system in a [`supervisor::Supervisor`] or correlate an [`Envelope`]'s sender.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize)]
 pub struct SystemUID(u16);
 
 impl SystemUID {
@@ -78,17 +95,142 @@ impl<T> Clone for Addr<T> {
   }
 }
 
+/// An envelope bundling a query with a reply address, turning the fire-and-forget [`Addr::send_msg`] into a
+/// request/response (“ask”) interaction.
+///
+/// A system sends a `Request<Q, R>` the same way it would send any other message; the receiving system does its
+/// work and calls [`Request::reply`] with the answer, which is delivered straight back to the requester without
+/// going through the receiving system’s own message queue.
+#[derive(Debug)]
+pub struct Request<Q, R> {
+  /// The query payload.
+  pub query: Q,
+  reply_addr: Addr<R>,
+}
+
+impl<Q, R> Request<Q, R> {
+  /// Reply to this request.
+  pub fn reply(&self, r: R) -> Result<(), SystemError> {
+    self.reply_addr.send_msg(r)
+  }
+}
+
+impl<Q: Clone, R> Clone for Request<Q, R> {
+  fn clone(&self) -> Self {
+    Request {
+      query: self.query.clone(),
+      reply_addr: self.reply_addr.clone(),
+    }
+  }
+}
+
+impl<Q, R> Addr<Request<Q, R>> {
+  /// Send a query and block until a reply arrives.
+  pub fn request(&self, query: Q) -> Result<R, SystemError> {
+    let (reply_addr, reply_queue) = system_init();
+    self.send_msg(Request { query, reply_addr })?;
+    reply_queue.recv().ok_or(SystemError::CannotSend)
+  }
+
+  /// Like [`Addr::request`], but give up with [`SystemError::Timeout`] instead of blocking forever.
+  pub fn request_timeout(&self, query: Q, timeout: Duration) -> Result<R, SystemError> {
+    let (reply_addr, reply_queue) = system_init();
+    self.send_msg(Request { query, reply_addr })?;
+
+    match reply_queue.recv_timeout(timeout) {
+      RecvOutcome::Msg(r) => Ok(r),
+      RecvOutcome::Timeout => Err(SystemError::Timeout),
+      RecvOutcome::Disconnected => Err(SystemError::CannotSend),
+    }
+  }
+}
+
+/// Unique identifier of a message, used to correlate an [`Ack`] (or any other reply) with the message that caused
+/// it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MsgId(u64);
+
+impl MsgId {
+  fn new() -> Self {
+    MsgId(thread_rng().gen())
+  }
+}
+
+impl fmt::Display for MsgId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    self.0.fmt(f)
+  }
+}
+
+/// A small acknowledgement that a message was received and handled, correlated to that message via [`MsgId`].
+#[derive(Clone, Copy, Debug)]
+pub struct Ack(pub MsgId);
+
+/// Wraps a message with delivery metadata: who sent it, a correlation id, and (if the sender wants one) where to
+/// deliver an acknowledgement.
+///
+/// Envelopes let a receiving system log provenance, ack successful handling, and correlate replies without the
+/// application having to thread sender addresses through every message variant by hand.
+#[derive(Debug)]
+pub struct Envelope<M> {
+  /// The wrapped payload.
+  pub msg: M,
+  /// Identity of the system that sent this message.
+  pub from: SystemUID,
+  /// Unique id of this message.
+  pub id: MsgId,
+  reply_to: Option<Addr<Ack>>,
+}
+
+impl<M> Envelope<M> {
+  /// Acknowledge this message back to its sender's reply address, if it provided one.
+  pub fn ack(&self) -> Result<(), SystemError> {
+    match &self.reply_to {
+      Some(addr) => addr.send_msg(Ack(self.id)),
+      None => Ok(()),
+    }
+  }
+}
+
+impl<M> Addr<Envelope<M>> {
+  /// Send `msg` wrapped in an [`Envelope`] that records `from`'s identity, a fresh [`MsgId`], and (if `from` keeps
+  /// an ack mailbox) an address the receiver can use to acknowledge the message.
+  pub fn send_msg_from<S, E>(&self, msg: M, from: &S) -> Result<(), SystemError>
+  where
+    S: System<M, E>,
+    E: Clone,
+  {
+    self.send_msg(Envelope {
+      msg,
+      from: from.system_uid(),
+      id: MsgId::new(),
+      reply_to: from.ack_addr(),
+    })
+  }
+}
+
+impl<M> MsgQueue<Envelope<M>> {
+  /// Wait for the next [`Envelope`], yielding its metadata (sender, id, ack address) alongside the payload. Same as
+  /// [`MsgQueue::recv`] on this queue, which also returns the whole envelope.
+  pub fn recv_envelope(&self) -> Option<Envelope<M>> {
+    self.recv()
+  }
+}
+
 /// Errors that might occur with [`System`] operations.
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub enum SystemError {
   /// Cannot send a message.
   CannotSend,
+  /// A [`Addr::request_timeout`] call didn’t get a reply in time.
+  Timeout,
 }
 
 impl fmt::Display for SystemError {
   fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
     match *self {
       SystemError::CannotSend => write!(f, "cannot send message"),
+      SystemError::Timeout => write!(f, "request timed out"),
     }
   }
 }
@@ -104,6 +246,35 @@ impl<T> MsgQueue<T> {
   pub fn recv(&self) -> Option<T> {
     self.receiver.recv().ok()
   }
+
+  /// Wait for a message, but give up after `timeout` instead of blocking forever.
+  ///
+  /// Unlike [`MsgQueue::recv`], a disconnected channel and an elapsed timeout are distinguishable outcomes; this
+  /// lets callers select between this queue and some other source of events (e.g. a filesystem watcher) without
+  /// mistaking “nothing yet” for “the system is gone”.
+  pub fn recv_timeout(&self, timeout: Duration) -> RecvOutcome<T> {
+    match self.receiver.recv_timeout(timeout) {
+      Ok(msg) => RecvOutcome::Msg(msg),
+      Err(mpsc::RecvTimeoutError::Timeout) => RecvOutcome::Timeout,
+      Err(mpsc::RecvTimeoutError::Disconnected) => RecvOutcome::Disconnected,
+    }
+  }
+
+  /// Check for a message without waiting at all, for polling a queue alongside some other source of events.
+  pub fn try_recv(&self) -> Option<T> {
+    self.receiver.try_recv().ok()
+  }
+}
+
+/// Outcome of [`MsgQueue::recv_timeout`].
+#[derive(Debug)]
+pub enum RecvOutcome<T> {
+  /// A message was received.
+  Msg(T),
+  /// No message arrived before the timeout elapsed.
+  Timeout,
+  /// The channel has disconnected; no further messages will ever arrive.
+  Disconnected,
 }
 
 /// Default implementation of a system initialization procedure.
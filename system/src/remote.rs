@@ -0,0 +1,219 @@
+//! Cross-process remote addresses.
+//!
+//! [`RemoteAddr`] offers the same `send_msg` surface as a local [`Addr`](crate::Addr), but ships flexbuffers-encoded
+//! messages over a TCP socket instead. [`connect_remote`] dials a listening peer; [`spawn_remote`] is the other
+//! end. [`SystemAddr`] wraps either kind of address behind one type.
+
+use crate::{system_init, Addr, MsgQueue, SystemError, SystemUID};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+  io::{self, Read, Write},
+  marker::PhantomData,
+  net::{TcpListener, TcpStream, ToSocketAddrs},
+  sync::{Arc, Mutex},
+  thread,
+};
+
+/// Write a length-prefixed, flexbuffers-encoded value to `stream`.
+fn write_frame<T: Serialize>(mut stream: impl Write, value: &T) -> io::Result<()> {
+  let bytes = flexbuffers::to_vec(value).map_err(io::Error::other)?;
+  stream.write_all(&(bytes.len() as u32).to_be_bytes())?;
+  stream.write_all(&bytes)
+}
+
+/// Read a length-prefixed, flexbuffers-encoded value from `stream`.
+fn read_frame<T: DeserializeOwned>(mut stream: impl Read) -> io::Result<T> {
+  let mut len_buf = [0u8; 4];
+  stream.read_exact(&mut len_buf)?;
+
+  let mut buf = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+  stream.read_exact(&mut buf)?;
+
+  flexbuffers::from_slice(&buf).map_err(io::Error::other)
+}
+
+/// An address of a [`System`](crate::System) living in another process.
+///
+/// Offers the same `send_msg` surface as a local [`Addr`](crate::Addr), but serializes messages and ships them
+/// over a socket instead of moving them through memory. The stream is behind a `Mutex` so that, like `Addr`,
+/// cloned handles can send concurrently from different threads without interleaving each other's frames.
+pub struct RemoteAddr<T> {
+  stream: Arc<Mutex<TcpStream>>,
+  peer_uid: SystemUID,
+  _marker: PhantomData<T>,
+}
+
+impl<T> RemoteAddr<T>
+where
+  T: Serialize + DeserializeOwned,
+{
+  /// Send `msg` to the peer process.
+  pub fn send_msg(&self, msg: T) -> Result<(), SystemError> {
+    let stream = self.stream.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    write_frame(&*stream, &msg).map_err(|_| SystemError::CannotSend)
+  }
+
+  /// [`SystemUID`] of the peer this address talks to.
+  pub fn peer_uid(&self) -> SystemUID {
+    self.peer_uid
+  }
+}
+
+impl<T> Clone for RemoteAddr<T> {
+  fn clone(&self) -> Self {
+    RemoteAddr {
+      stream: self.stream.clone(),
+      peer_uid: self.peer_uid,
+      _marker: PhantomData,
+    }
+  }
+}
+
+/// Either a local [`Addr`](crate::Addr) or a [`RemoteAddr`], built with `.into()` from either one.
+pub enum SystemAddr<T> {
+  /// The target system lives in this process.
+  Local(Addr<T>),
+  /// The target system lives in another process, reached over a [`RemoteAddr`].
+  Remote(RemoteAddr<T>),
+}
+
+impl<T> SystemAddr<T>
+where
+  T: Serialize + DeserializeOwned,
+{
+  /// Send `msg`, locally or over the wire depending on which variant this is.
+  pub fn send_msg(&self, msg: T) -> Result<(), SystemError> {
+    match self {
+      SystemAddr::Local(addr) => addr.send_msg(msg),
+      SystemAddr::Remote(addr) => addr.send_msg(msg),
+    }
+  }
+}
+
+impl<T> Clone for SystemAddr<T> {
+  fn clone(&self) -> Self {
+    match self {
+      SystemAddr::Local(addr) => SystemAddr::Local(addr.clone()),
+      SystemAddr::Remote(addr) => SystemAddr::Remote(addr.clone()),
+    }
+  }
+}
+
+impl<T> From<Addr<T>> for SystemAddr<T> {
+  fn from(addr: Addr<T>) -> Self {
+    SystemAddr::Local(addr)
+  }
+}
+
+impl<T> From<RemoteAddr<T>> for SystemAddr<T> {
+  fn from(addr: RemoteAddr<T>) -> Self {
+    SystemAddr::Remote(addr)
+  }
+}
+
+/// Exchange [`SystemUID`]s with the peer at the other end of `stream`, so messages can be routed once the
+/// connection is up.
+fn handshake(stream: &TcpStream, my_uid: SystemUID) -> io::Result<SystemUID> {
+  write_frame(stream, &my_uid)?;
+  read_frame(stream)
+}
+
+/// Connect to a peer already listening at `peer_addr`, perform the handshake, and return a [`RemoteAddr`] plus a
+/// [`MsgQueue`] fed by whatever the peer sends back. See [`spawn_remote`] for the listener side.
+pub fn connect_remote<T, U>(
+  my_uid: SystemUID,
+  peer_addr: impl ToSocketAddrs,
+) -> io::Result<(RemoteAddr<T>, MsgQueue<U>)>
+where
+  T: Serialize + DeserializeOwned,
+  U: Serialize + DeserializeOwned + Send + 'static,
+{
+  let stream = TcpStream::connect(peer_addr)?;
+  let peer_uid = handshake(&stream, my_uid)?;
+  let (remote_addr, msg_queue) = wrap_stream(stream, peer_uid)?;
+
+  Ok((remote_addr, msg_queue))
+}
+
+/// Listen on `bind_addr` for a single incoming connection, perform the handshake, and hand the resulting
+/// [`RemoteAddr`]/[`MsgQueue`] pair to `on_connect`. Runs on a dedicated thread; joining the returned handle waits
+/// until `on_connect` has returned, or until accepting or setting up the connection has failed, in which case the
+/// error is logged and `on_connect` is never called.
+pub fn spawn_remote<T, U>(
+  bind_addr: impl ToSocketAddrs,
+  my_uid: SystemUID,
+  on_connect: impl FnOnce(RemoteAddr<T>, MsgQueue<U>) + Send + 'static,
+) -> io::Result<thread::JoinHandle<()>>
+where
+  T: Serialize + DeserializeOwned + Send + 'static,
+  U: Serialize + DeserializeOwned + Send + 'static,
+{
+  let listener = TcpListener::bind(bind_addr)?;
+
+  Ok(thread::spawn(move || {
+    let (stream, peer) = match listener.accept() {
+      Ok(conn) => conn,
+
+      Err(err) => {
+        log::error!("cannot accept remote connection: {}", err);
+        return;
+      }
+    };
+
+    let peer_uid = match handshake(&stream, my_uid) {
+      Ok(uid) => uid,
+
+      Err(err) => {
+        log::error!("remote handshake with {} failed: {}", peer, err);
+        return;
+      }
+    };
+
+    match wrap_stream(stream, peer_uid) {
+      Ok((remote_addr, msg_queue)) => on_connect(remote_addr, msg_queue),
+      Err(err) => log::error!("cannot set up remote connection to {}: {}", peer, err),
+    }
+  }))
+}
+
+/// Wrap `stream` (already past the handshake) into a [`RemoteAddr`] paired with a [`MsgQueue`] fed by a background
+/// thread. Shared by [`connect_remote`] and [`spawn_remote`].
+fn wrap_stream<T, U>(stream: TcpStream, peer_uid: SystemUID) -> io::Result<(RemoteAddr<T>, MsgQueue<U>)>
+where
+  U: DeserializeOwned + Send + 'static,
+{
+  let (addr, msg_queue) = system_init();
+  let recv_stream = stream.try_clone()?;
+  thread::spawn(move || forward_incoming(recv_stream, addr));
+
+  Ok((
+    RemoteAddr {
+      stream: Arc::new(Mutex::new(stream)),
+      peer_uid,
+      _marker: PhantomData,
+    },
+    msg_queue,
+  ))
+}
+
+/// Read frames off `stream` until it closes or a message fails to decode, forwarding each one into `addr`.
+fn forward_incoming<U>(stream: TcpStream, addr: Addr<U>)
+where
+  U: DeserializeOwned,
+{
+  loop {
+    match read_frame(&stream) {
+      Ok(msg) => {
+        if addr.send_msg(msg).is_err() {
+          // the local receiver is gone; no point keeping the connection alive
+          break;
+        }
+      }
+
+      Err(err) => {
+        log::debug!("remote connection closed: {}", err);
+        break;
+      }
+    }
+  }
+}
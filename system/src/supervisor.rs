@@ -0,0 +1,297 @@
+//! Supervision trees.
+//!
+//! A [`Supervisor`] owns a set of children identified by [`SystemUID`] and reacts to their exit according to a
+//! [`RestartPolicy`]: it can restart just the child that died, restart every child it tracks, or give up and
+//! escalate once a restart budget has been exhausted. Escalation means shutting down every remaining child and, if
+//! the supervisor itself has a parent, reporting its own exit up the tree — which is how a crash at the bottom of a
+//! supervision tree eventually reaches something that can decide what to do about it.
+
+use crate::{dispatcher::Dispatcher, system_init, Addr, MsgQueue, System, SystemError, SystemUID};
+use std::{
+  collections::HashMap,
+  time::{Duration, Instant},
+};
+
+/// Messages a [`Supervisor`] understands.
+#[derive(Clone, Debug)]
+pub enum SupervisorMsg {
+  /// A tracked child reported that it exited.
+  ChildExited(SystemUID),
+  /// Poison pill: shut down every tracked child, then this supervisor itself.
+  Shutdown,
+}
+
+/// Events a [`Supervisor`] publishes.
+#[derive(Clone, Debug)]
+pub enum SupervisorEvent {
+  /// A child exceeded its restart budget; the supervisor escalated instead of restarting it again.
+  Escalated(SystemUID),
+}
+
+/// How a [`Supervisor`] reacts when a child exits.
+#[derive(Clone, Debug)]
+pub enum RestartPolicy {
+  /// Restart only the child that exited.
+  OneForOne,
+  /// Restart every currently-tracked child whenever any one of them exits.
+  OneForAll,
+  /// Restart the child that exited, but only up to `max_restarts` times within a rolling `within` window; once the
+  /// budget is exhausted, escalate instead.
+  RestartOnFailure { max_restarts: usize, within: Duration },
+}
+
+/// Everything a [`Supervisor`] needs to bring a child back to life: a way to shut down the current instance, and a
+/// factory that spawns a fresh one and hands back a shutdown closure for it.
+struct Child {
+  shutdown: Box<dyn Fn() -> Result<(), SystemError> + Send>,
+  factory: Box<dyn Fn() -> Box<dyn Fn() -> Result<(), SystemError> + Send> + Send>,
+  /// Timestamps of past restarts, used to enforce [`RestartPolicy::RestartOnFailure`]'s budget.
+  restarts: Vec<Instant>,
+}
+
+/// Supervises a set of children, restarting or escalating as dictated by its [`RestartPolicy`].
+pub struct Supervisor {
+  uid: SystemUID,
+  policy: RestartPolicy,
+  addr: Addr<SupervisorMsg>,
+  msg_queue: MsgQueue<SupervisorMsg>,
+  children: HashMap<SystemUID, Child>,
+  dispatcher: Dispatcher<SupervisorEvent>,
+  /// The supervisor to notify, if any, should this one have to escalate a failure of its own.
+  parent: Option<Addr<SupervisorMsg>>,
+}
+
+impl Supervisor {
+  /// Create a new, parentless [`Supervisor`] applying `policy` to all of its children.
+  pub fn new(uid: SystemUID, policy: RestartPolicy) -> Self {
+    let (addr, msg_queue) = system_init();
+
+    Self {
+      uid,
+      policy,
+      addr,
+      msg_queue,
+      children: HashMap::new(),
+      dispatcher: Dispatcher::new(),
+      parent: None,
+    }
+  }
+
+  /// Attach this supervisor under `parent`, so that an escalation is forwarded up the tree.
+  pub fn with_parent(mut self, parent: Addr<SupervisorMsg>) -> Self {
+    self.parent = Some(parent);
+    self
+  }
+
+  /// Register a child under supervision.
+  ///
+  /// `shutdown` sends a poison pill to the currently running instance; `factory` (re)spawns the child from scratch
+  /// and returns a fresh `shutdown` closure for the newly spawned instance. Both are provided by the caller because
+  /// the concrete message type of a child is opaque to the supervisor.
+  pub fn supervise(
+    &mut self,
+    child_uid: SystemUID,
+    shutdown: impl Fn() -> Result<(), SystemError> + Send + 'static,
+    factory: impl Fn() -> Box<dyn Fn() -> Result<(), SystemError> + Send> + Send + 'static,
+  ) {
+    self.children.insert(
+      child_uid,
+      Child {
+        shutdown: Box::new(shutdown),
+        factory: Box::new(factory),
+        restarts: Vec::new(),
+      },
+    );
+  }
+
+  /// Start the supervisor loop: react to children exiting until a [`SupervisorMsg::Shutdown`] is received, or
+  /// until this supervisor itself has to escalate past its own restart budget.
+  pub fn start(mut self) {
+    loop {
+      match self.msg_queue.recv() {
+        Some(SupervisorMsg::ChildExited(uid)) => {
+          if self.handle_child_exit(uid) {
+            self.shutdown_children();
+            break;
+          }
+        }
+
+        Some(SupervisorMsg::Shutdown) | None => {
+          self.shutdown_children();
+          break;
+        }
+      }
+    }
+  }
+
+  /// Handle a child's exit. Returns `true` if this supervisor escalated and must now shut itself down.
+  fn handle_child_exit(&mut self, uid: SystemUID) -> bool {
+    match self.policy {
+      RestartPolicy::OneForOne => {
+        self.restart_child(uid);
+        false
+      }
+
+      RestartPolicy::OneForAll => {
+        let uids: Vec<_> = self.children.keys().copied().collect();
+        for uid in uids {
+          self.restart_child(uid);
+        }
+        false
+      }
+
+      RestartPolicy::RestartOnFailure { max_restarts, within } => {
+        self.restart_with_budget(uid, max_restarts, within)
+      }
+    }
+  }
+
+  fn restart_child(&mut self, uid: SystemUID) {
+    if let Some(child) = self.children.get_mut(&uid) {
+      log::info!("supervisor {} restarting child {}", self.uid, uid);
+
+      // the child that triggered this may already be dead, but siblings restarted under OneForAll are still
+      // running and need to be told to stop before we replace their shutdown closure, or the old instance keeps
+      // running alongside the replacement
+      if (child.shutdown)().is_err() {
+        log::warn!("could not deliver shutdown to child {} before restarting it", uid);
+      }
+
+      child.shutdown = (child.factory)();
+    }
+  }
+
+  /// Returns `true` if the child's restart budget is exhausted and this supervisor must escalate.
+  fn restart_with_budget(&mut self, uid: SystemUID, max_restarts: usize, within: Duration) -> bool {
+    let Some(child) = self.children.get_mut(&uid) else {
+      return false;
+    };
+
+    let now = Instant::now();
+    child.restarts.retain(|t| now.duration_since(*t) <= within);
+
+    if child.restarts.len() >= max_restarts {
+      log::error!(
+        "child {} exceeded its restart budget ({} within {:?}); escalating",
+        uid,
+        max_restarts,
+        within
+      );
+      self.escalate(uid);
+      return true;
+    }
+
+    child.restarts.push(now);
+
+    if (child.shutdown)().is_err() {
+      log::warn!("could not deliver shutdown to child {} before restarting it", uid);
+    }
+
+    child.shutdown = (child.factory)();
+    false
+  }
+
+  /// Give up on restarting `uid` and notify our own parent; the caller is responsible for shutting down the
+  /// remaining children and stopping this supervisor's own loop.
+  fn escalate(&mut self, uid: SystemUID) {
+    self.publish(SupervisorEvent::Escalated(uid));
+
+    if let Some(parent) = &self.parent {
+      let _ = parent.send_msg(SupervisorMsg::ChildExited(self.uid));
+    }
+  }
+
+  fn shutdown_children(&self) {
+    for (uid, child) in &self.children {
+      if (child.shutdown)().is_err() {
+        log::warn!("could not deliver shutdown to child {}", uid);
+      }
+    }
+  }
+}
+
+impl System<SupervisorEvent> for Supervisor {
+  type Addr = Addr<SupervisorMsg>;
+
+  fn system_addr(&self) -> Addr<SupervisorMsg> {
+    self.addr.clone()
+  }
+
+  fn system_uid(&self) -> SystemUID {
+    self.uid
+  }
+
+  fn startup(self) {
+    let _ = std::thread::spawn(move || {
+      self.start();
+    });
+  }
+
+  fn subscribe(&mut self, addr: Addr<SupervisorEvent>) {
+    self.dispatcher.subscribe_all(addr);
+  }
+
+  fn publish(&self, event: SupervisorEvent) {
+    self.dispatcher.publish_topic("supervisor.escalated", event);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+
+  fn noop_child() -> (SystemUID, Arc<AtomicUsize>) {
+    let factory_calls = Arc::new(AtomicUsize::new(0));
+    (SystemUID::new(), factory_calls)
+  }
+
+  #[test]
+  fn restart_with_budget_restarts_until_exhausted_then_escalates() {
+    let mut supervisor = Supervisor::new(SystemUID::new(), RestartPolicy::OneForOne);
+    let (uid, factory_calls) = noop_child();
+
+    {
+      let factory_calls = factory_calls.clone();
+      supervisor.supervise(
+        uid,
+        || Ok(()),
+        move || {
+          factory_calls.fetch_add(1, Ordering::SeqCst);
+          Box::new(|| Ok(()))
+        },
+      );
+    }
+
+    // two restarts fit inside the budget
+    assert!(!supervisor.restart_with_budget(uid, 2, Duration::from_secs(60)));
+    assert!(!supervisor.restart_with_budget(uid, 2, Duration::from_secs(60)));
+    // the third exit within the same window exceeds it: escalate instead of restarting again
+    assert!(supervisor.restart_with_budget(uid, 2, Duration::from_secs(60)));
+
+    assert_eq!(factory_calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn restart_with_budget_window_slides_old_restarts_out() {
+    let mut supervisor = Supervisor::new(SystemUID::new(), RestartPolicy::OneForOne);
+    let (uid, _) = noop_child();
+
+    supervisor.supervise(uid, || Ok(()), || Box::new(|| Ok(())));
+
+    // a window so short that, by the time the next restart is considered, every prior one has already aged out, so
+    // the budget of 1 never actually trips
+    assert!(!supervisor.restart_with_budget(uid, 1, Duration::from_millis(1)));
+    std::thread::sleep(Duration::from_millis(5));
+    assert!(!supervisor.restart_with_budget(uid, 1, Duration::from_millis(1)));
+  }
+
+  #[test]
+  fn restart_with_budget_on_unknown_child_does_not_escalate() {
+    let mut supervisor = Supervisor::new(SystemUID::new(), RestartPolicy::OneForOne);
+    assert!(!supervisor.restart_with_budget(SystemUID::new(), 1, Duration::from_secs(60)));
+  }
+}